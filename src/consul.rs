@@ -0,0 +1,196 @@
+use crate::firewall::Firewall;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const BANS_PREFIX: &str = "sentinel/bans/";
+
+/// One entry in the shared blocklist, mirrored verbatim into the Consul KV
+/// value so every node can tell who banned an IP and when it stops mattering.
+#[derive(Debug, Serialize, Deserialize)]
+struct BanEntry {
+    reason: String,
+    node_id: String,
+    expires_at: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct KvEntry {
+    #[serde(rename = "Key")]
+    key: String,
+    #[serde(rename = "Value")]
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionCreateResponse {
+    #[serde(rename = "ID")]
+    id: String,
+}
+
+/// Shares bans across a fleet of nginx-sentinel nodes via Consul KV. Each ban
+/// is written under a session with a TTL equal to the ban duration, so the
+/// key self-expires instead of needing a matching unban.
+pub struct ClusterSync {
+    client: reqwest::Client,
+    consul_url: String,
+    node_id: String,
+}
+
+impl ClusterSync {
+    pub fn new(consul_url: String, node_id: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            consul_url,
+            node_id,
+        }
+    }
+
+    /// Publish a ban this node just issued so the rest of the fleet picks it up.
+    pub async fn publish_ban(&self, ip: &str, reason: &str, ban_time_seconds: usize) -> Result<()> {
+        let session_id = self.create_session(ban_time_seconds).await?;
+
+        let expires_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            + ban_time_seconds as u64;
+
+        let entry = BanEntry {
+            reason: reason.to_string(),
+            node_id: self.node_id.clone(),
+            expires_at,
+        };
+
+        let url = format!("{}/v1/kv/{}{}?acquire={}", self.consul_url, BANS_PREFIX, ip, session_id);
+        let resp = self.client.put(&url).json(&entry).send().await?;
+
+        if !resp.status().is_success() {
+            return Err(anyhow!(
+                "Consul KV write failed for {}: {}",
+                ip,
+                resp.status()
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn create_session(&self, ttl_seconds: usize) -> Result<String> {
+        let url = format!("{}/v1/session/create", self.consul_url);
+        let body = serde_json::json!({
+            "TTL": format!("{}s", ttl_seconds),
+            "Behavior": "delete",
+        });
+
+        let resp = self.client.put(&url).json(&body).send().await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("Consul session create failed: {}", resp.status()));
+        }
+
+        let parsed: SessionCreateResponse = resp.json().await?;
+        Ok(parsed.id)
+    }
+
+    /// Long-poll the `sentinel/bans/` prefix and apply any ban authored by a
+    /// different node to the local firewall. Runs until the process exits.
+    pub fn spawn_watcher(self: Arc<Self>, fw: Arc<dyn Firewall>) {
+        tokio::spawn(async move {
+            let mut index: u64 = 0;
+            loop {
+                match self.watch_once(index, &fw).await {
+                    Ok(new_index) => index = new_index,
+                    Err(e) => {
+                        log::error!("Consul blocking query failed: {}", e);
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                }
+            }
+        });
+    }
+
+    async fn watch_once(&self, index: u64, fw: &Arc<dyn Firewall>) -> Result<u64> {
+        let url = format!(
+            "{}/v1/kv/{}?recurse=true&index={}&wait=5m",
+            self.consul_url, BANS_PREFIX, index
+        );
+        let resp = self.client.get(&url).send().await?;
+
+        let next_index = resp
+            .headers()
+            .get("X-Consul-Index")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(index);
+
+        // Consul returns 404 when the prefix has no keys yet; treat that as
+        // "nothing to apply" rather than an error.
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(next_index);
+        }
+        if !resp.status().is_success() {
+            return Err(anyhow!("Consul KV read failed: {}", resp.status()));
+        }
+
+        let entries: Vec<KvEntry> = resp.json().await.unwrap_or_default();
+
+        for kv in entries {
+            let Some(ip) = kv.key.strip_prefix(BANS_PREFIX) else {
+                continue;
+            };
+            let Some(raw_value) = kv.value else {
+                continue;
+            };
+
+            let decoded = match base64_decode(&raw_value) {
+                Ok(d) => d,
+                Err(e) => {
+                    log::warn!("Failed to decode Consul ban entry for {}: {}", ip, e);
+                    continue;
+                }
+            };
+
+            let entry: BanEntry = match serde_json::from_slice(&decoded) {
+                Ok(e) => e,
+                Err(e) => {
+                    log::warn!("Failed to parse Consul ban entry for {}: {}", ip, e);
+                    continue;
+                }
+            };
+
+            // Skip keys we authored ourselves to avoid re-banning in a loop.
+            if entry.node_id == self.node_id {
+                continue;
+            }
+
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            if entry.expires_at <= now {
+                continue;
+            }
+            let remaining = (entry.expires_at - now) as usize;
+
+            log::info!(
+                "🌐 Applying remote ban from node {}: {} ({}s remaining)",
+                entry.node_id,
+                ip,
+                remaining
+            );
+            if let Err(e) = fw.ban_ip(ip, remaining) {
+                log::error!("❌ Failed to apply remote ban for {}: {}", ip, e);
+            }
+        }
+
+        Ok(next_index)
+    }
+}
+
+fn base64_decode(input: &str) -> Result<Vec<u8>> {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD
+        .decode(input)
+        .map_err(|e| anyhow!("invalid base64: {}", e))
+}
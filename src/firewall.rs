@@ -1,7 +1,17 @@
 use anyhow::{Result, anyhow};
 use log::info;
+use netstat2::{AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, get_sockets_info};
+use std::net::{IpAddr, Ipv4Addr};
 use std::process::Command;
 
+/// Abstraction over the underlying packet-filter backend used to drop traffic
+/// from banned IPs. Implementations are responsible for their own one-time
+/// setup (tables/chains/sets/rules) in `new()` and for adding a single IP to
+/// the drop set in `ban_ip`.
+pub trait Firewall: Send + Sync {
+    fn ban_ip(&self, ip: &str, duration: usize) -> Result<()>;
+}
+
 pub struct IpSetFirewall {
     set_name: String,
 }
@@ -64,8 +74,10 @@ impl IpSetFirewall {
 
         Ok(Self { set_name })
     }
+}
 
-    pub fn ban_ip(&self, ip: &str, duration: usize) -> Result<()> {
+impl Firewall for IpSetFirewall {
+    fn ban_ip(&self, ip: &str, duration: usize) -> Result<()> {
         // Command: ipset add siest_sentinel 1.2.3.4 timeout 3600 -exist
         let output = Command::new("ipset")
             .args(&[
@@ -88,3 +100,186 @@ impl IpSetFirewall {
         }
     }
 }
+
+/// Firewall backend for nftables-only systems (no ipset/iptables compat layer).
+/// Bans are tracked in a named set with the `timeout` flag so expiry is
+/// handled entirely by the kernel, mirroring how `IpSetFirewall` leans on
+/// ipset's own timeout support.
+pub struct NftablesFirewall {
+    table: String,
+    set_name: String,
+}
+
+impl NftablesFirewall {
+    pub fn new() -> Result<Self> {
+        let table = "filter".to_string();
+        let set_name = "siest_sentinel".to_string();
+
+        // 1. Ensure the inet table exists (no-op if already present)
+        let table_output = Command::new("nft")
+            .args(&["add", "table", "inet", &table])
+            .output()
+            .map_err(|e| anyhow!("Failed to execute nft: {}", e))?;
+
+        if !table_output.status.success() {
+            return Err(anyhow!(
+                "Error creating nftables table: {}",
+                String::from_utf8_lossy(&table_output.stderr)
+            ));
+        }
+
+        // 2. Ensure the input chain exists, hooked to filter incoming traffic
+        let chain_output = Command::new("nft")
+            .args(&[
+                "add",
+                "chain",
+                "inet",
+                &table,
+                "input",
+                "{ type filter hook input priority 0; }",
+            ])
+            .output()
+            .map_err(|e| anyhow!("Failed to execute nft: {}", e))?;
+
+        if !chain_output.status.success() {
+            return Err(anyhow!(
+                "Error creating nftables chain: {}",
+                String::from_utf8_lossy(&chain_output.stderr)
+            ));
+        }
+
+        // 3. Ensure the named set exists with the timeout flag
+        let set_output = Command::new("nft")
+            .args(&[
+                "add",
+                "set",
+                "inet",
+                &table,
+                &set_name,
+                "{ type ipv4_addr; flags timeout; }",
+            ])
+            .output()
+            .map_err(|e| anyhow!("Failed to execute nft: {}", e))?;
+
+        if !set_output.status.success() {
+            return Err(anyhow!(
+                "Error creating nftables set: {}",
+                String::from_utf8_lossy(&set_output.stderr)
+            ));
+        }
+
+        // 4. Ensure a drop rule referencing the set exists
+        let check = Command::new("nft")
+            .args(&["list", "chain", "inet", &table, "input"])
+            .output()
+            .map_err(|e| anyhow!("Failed to execute nft: {}", e))?;
+        let rule_present = String::from_utf8_lossy(&check.stdout).contains(&set_name);
+
+        if !rule_present {
+            Command::new("nft")
+                .args(&[
+                    "add",
+                    "rule",
+                    "inet",
+                    &table,
+                    "input",
+                    "ip",
+                    "saddr",
+                    &format!("@{}", set_name),
+                    "drop",
+                ])
+                .output()
+                .map_err(|e| anyhow!("Failed to update nftables rule: {}", e))?;
+            info!("✅ Firewall: nftables drop rule installed successfully.");
+        } else {
+            info!("✅ Firewall: nftables drop rule already active.");
+        }
+
+        Ok(Self { table, set_name })
+    }
+}
+
+impl Firewall for NftablesFirewall {
+    fn ban_ip(&self, ip: &str, duration: usize) -> Result<()> {
+        // `ip` is spliced directly into the nft element spec below, so make
+        // sure it's actually an IPv4 address first — otherwise a malformed
+        // value from the log line could smuggle extra nft statements into
+        // this invocation.
+        let ip: Ipv4Addr = ip
+            .parse()
+            .map_err(|e| anyhow!("Invalid IP for nftables ban {}: {}", ip, e))?;
+
+        // Command: nft add element inet filter siest_sentinel { 1.2.3.4 timeout 3600s }
+        let element = format!("{{ {} timeout {}s }}", ip, duration);
+        let output = Command::new("nft")
+            .args(&[
+                "add",
+                "element",
+                "inet",
+                &self.table,
+                &self.set_name,
+                &element,
+            ])
+            .output()?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Failed to ban IP: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+}
+
+/// Tear down any already-established TCP connections from `ip`. Adding an IP
+/// to the drop set only blocks *new* packets, so a keep-alive or slowloris
+/// connection opened before the ban survives unless we kill it explicitly.
+/// Opt-in via `kill_existing_connections` since it's a more invasive action
+/// than the drop rule itself.
+pub fn kill_existing_connections(ip: &str) -> Result<()> {
+    let target: IpAddr = ip
+        .parse()
+        .map_err(|e| anyhow!("Invalid IP for connection kill {}: {}", ip, e))?;
+
+    // Enumeration is informational only (so we can log how many connections
+    // we're about to tear down) — it must never gate the actual kill below,
+    // since a failure to list sockets says nothing about whether `ss`/
+    // `conntrack` would succeed.
+    match get_sockets_info(AddressFamilyFlags::IPV4, ProtocolFlags::TCP) {
+        Ok(sockets) => {
+            let matching = sockets
+                .iter()
+                .filter(|socket| match &socket.protocol_socket_info {
+                    ProtocolSocketInfo::Tcp(tcp) => tcp.remote_addr == target,
+                    _ => false,
+                })
+                .count();
+            info!(
+                "🔪 Killing {} existing connection(s) from banned IP: {}",
+                matching, ip
+            );
+        }
+        Err(e) => {
+            log::warn!(
+                "Failed to enumerate sockets for {} (killing connections anyway): {}",
+                ip,
+                e
+            );
+        }
+    }
+
+    // Drop the live sockets...
+    if let Err(e) = Command::new("ss").args(&["-K", "dst", ip]).output() {
+        log::warn!("Failed to run `ss -K` for {}: {}", ip, e);
+    }
+
+    // ...and purge the conntrack entry so a retried connection from the same
+    // 4-tuple isn't waved through as an existing, already-accepted flow.
+    if let Err(e) = Command::new("conntrack").args(&["-D", "-s", ip]).output() {
+        log::warn!("Failed to run `conntrack -D` for {}: {}", ip, e);
+    }
+
+    Ok(())
+}
@@ -1,13 +1,17 @@
+mod consul;
 mod firewall;
 mod parser;
+mod stun;
+mod systemd;
 
 use anyhow::Result;
 use dashmap::DashMap;
 use linemux::MuxedLines;
 use std::sync::{Arc, RwLock};
 use std::time::Instant;
+use systemd::ServiceStats;
 
-struct IpState {
+pub(crate) struct IpState {
     count: u32,
     first_seen: Instant,
 }
@@ -25,13 +29,49 @@ async fn main() -> Result<()> {
     log::info!("🚀 Rust Sentinel started...");
 
     // 2. Setup Modules
-    let fw = firewall::IpSetFirewall::new().expect("Failed to initialize Firewall");
     let parser = parser::Parser::new();
-    let detector_state = DashMap::new();
+    let detector_state = Arc::new(DashMap::new());
+    let stats = Arc::new(ServiceStats::default());
 
     // Get config access
     let config_lock = parser.get_config();
     let log_path = config_lock.read().unwrap().log_path.clone();
+    let firewall_backend = config_lock.read().unwrap().firewall_backend.clone();
+
+    let fw: Arc<dyn firewall::Firewall> = match firewall_backend.as_str() {
+        "nftables" => Arc::new(
+            firewall::NftablesFirewall::new().expect("Failed to initialize Firewall"),
+        ),
+        _ => Arc::new(firewall::IpSetFirewall::new().expect("Failed to initialize Firewall")),
+    };
+
+    // Optional cross-node ban propagation via Consul KV
+    let cluster_sync: Option<Arc<consul::ClusterSync>> = {
+        let cfg = config_lock.read().unwrap();
+        if cfg.cluster_sync_enabled {
+            let cs = Arc::new(consul::ClusterSync::new(
+                cfg.consul_url.clone(),
+                cfg.node_id.clone(),
+            ));
+            cs.clone().spawn_watcher(fw.clone());
+            log::info!("🌐 Consul ban propagation enabled (node_id={})", cfg.node_id);
+            Some(cs)
+        } else {
+            None
+        }
+    };
+
+    // Optional self-ban prevention: learn our own public IP via STUN and
+    // keep it whitelisted even behind NAT / dynamic cloud addressing. Kept
+    // separate from `SecurityConfig` (see `is_whitelisted`) so a hot config
+    // reload can't silently drop the discovered address.
+    let discovered_ip: Arc<RwLock<Option<String>>> = Arc::new(RwLock::new(None));
+    {
+        let cfg = config_lock.read().unwrap();
+        if cfg.stun_discovery_enabled {
+            stun::spawn_refresh(discovered_ip.clone(), cfg.stun_server.clone());
+        }
+    }
 
     // 4. Log Watcher (Using Linemux to support log rotation)
     let mut lines = MuxedLines::new()?;
@@ -45,20 +85,19 @@ async fn main() -> Result<()> {
         std::process::exit(1);
     }
 
+    // Firewall initialized, config loaded, log file attached: we're ready.
+    systemd::notify_ready();
+    systemd::spawn_watchdog(stats.clone(), detector_state.clone());
+
     // 5. Main Loop
     while let Ok(Some(line)) = lines.next_line().await {
         let log_text = line.line();
+        stats.record_line();
 
         // Parsing
         match parser.parse_line(log_text) {
             parser::LogStatus::InstantBan(ip, reason) => {
-                // Check Whitelist
-                let is_whitelisted = {
-                    let config = config_lock.read().unwrap();
-                    config.whitelist.contains(&ip)
-                };
-
-                if is_whitelisted {
+                if is_whitelisted(&config_lock, &discovered_ip, &ip) {
                     log::debug!("⚪ Whitelist Activity: {}", ip);
                     continue;
                 }
@@ -69,27 +108,35 @@ async fn main() -> Result<()> {
                 log::error!("🚨 [INSTANT BAN TRIGGERED] IP: {} | Reason: {}", ip, reason);
 
                 match fw.ban_ip(&ip, ban_time_seconds) {
-                    Ok(_) => log::error!(
-                        "⛔ INSTANT BANNED: {} (Duration: {}s)",
-                        ip,
-                        ban_time_seconds
-                    ),
+                    Ok(_) => {
+                        log::error!(
+                            "⛔ INSTANT BANNED: {} (Duration: {}s)",
+                            ip,
+                            ban_time_seconds
+                        );
+                        stats.record_ban();
+                        systemd::report_status(&stats, &detector_state);
+                        publish_ban_async(&cluster_sync, &ip, &reason, ban_time_seconds);
+                        maybe_kill_connections(&config_lock, &ip);
+                    }
                     Err(e) => log::error!("❌ Failed to Ban IP {}: {}", ip, e),
                 }
             }
             parser::LogStatus::Suspicious(ip, reason) => {
-                // Check Whitelist
-                let is_whitelisted = {
-                    let config = config_lock.read().unwrap();
-                    config.whitelist.contains(&ip)
-                };
-
-                if is_whitelisted {
+                if is_whitelisted(&config_lock, &discovered_ip, &ip) {
                     log::debug!("⚪ Whitelist Activity: {}", ip);
                     continue;
                 }
 
-                process_attack(&ip, &reason, &detector_state, &fw, &config_lock);
+                process_attack(
+                    &ip,
+                    &reason,
+                    &detector_state,
+                    fw.as_ref(),
+                    &config_lock,
+                    &stats,
+                    &cluster_sync,
+                );
             }
             parser::LogStatus::Clean => {
                 // Do nothing for clean requests
@@ -97,6 +144,7 @@ async fn main() -> Result<()> {
         }
     }
 
+    systemd::notify_stopping();
     Ok(())
 }
 
@@ -104,8 +152,10 @@ fn process_attack(
     ip: &str,
     reason: &str,
     state: &DashMap<String, IpState>,
-    fw: &firewall::IpSetFirewall,
+    fw: &dyn firewall::Firewall,
     config_lock: &Arc<RwLock<parser::SecurityConfig>>,
+    stats: &Arc<ServiceStats>,
+    cluster_sync: &Option<Arc<consul::ClusterSync>>,
 ) {
     // Read config snapshot for dynamic values
     let (max_retries, window_seconds, ban_time_seconds) = {
@@ -143,8 +193,68 @@ fn process_attack(
 
         // Execute Ban
         match fw.ban_ip(ip, ban_time_seconds) {
-            Ok(_) => log::error!("⛔ BANNED: {} (Duration: {}s)", ip, ban_time_seconds),
+            Ok(_) => {
+                log::error!("⛔ BANNED: {} (Duration: {}s)", ip, ban_time_seconds);
+                stats.record_ban();
+                systemd::report_status(stats, state);
+                publish_ban_async(cluster_sync, ip, reason, ban_time_seconds);
+                maybe_kill_connections(config_lock, ip);
+            }
             Err(e) => log::error!("❌ Failed to Ban IP {}: {}", ip, e),
         }
     }
 }
+
+/// Whether `ip` should be exempt from banning. Checks both the config file's
+/// `whitelist` (hot-reloadable, replaced wholesale on every config change)
+/// and the separately-tracked STUN-discovered address (not subject to that
+/// reload, so a config edit can never transiently un-whitelist our own IP).
+fn is_whitelisted(
+    config_lock: &Arc<RwLock<parser::SecurityConfig>>,
+    discovered_ip: &Arc<RwLock<Option<String>>>,
+    ip: &str,
+) -> bool {
+    let in_config = config_lock.read().unwrap().whitelist.iter().any(|w| w == ip);
+    let is_self = discovered_ip.read().unwrap().as_deref() == Some(ip);
+    in_config || is_self
+}
+
+/// If enabled in config, tear down any established connections the banned IP
+/// already holds open so the ban takes effect immediately instead of only on
+/// the next new connection attempt. Runs off the tokio worker via
+/// `spawn_blocking` since it does a full socket enumeration plus two
+/// blocking `Command` spawns (`ss`, `conntrack`) — on the hot path after
+/// every ban, that's exactly the moment an active flood can't afford to
+/// have the log-processing loop stall on it.
+fn maybe_kill_connections(config_lock: &Arc<RwLock<parser::SecurityConfig>>, ip: &str) {
+    let kill_existing_connections = config_lock.read().unwrap().kill_existing_connections;
+    if kill_existing_connections {
+        let ip = ip.to_string();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = firewall::kill_existing_connections(&ip) {
+                log::error!("❌ Failed to kill existing connections for {}: {}", ip, e);
+            }
+        });
+    }
+}
+
+/// Fire off the Consul KV write without blocking the caller; ban propagation
+/// is best-effort and shouldn't slow down the hot path that's busy banning
+/// locally.
+fn publish_ban_async(
+    cluster_sync: &Option<Arc<consul::ClusterSync>>,
+    ip: &str,
+    reason: &str,
+    ban_time_seconds: usize,
+) {
+    if let Some(cs) = cluster_sync {
+        let cs = cs.clone();
+        let ip = ip.to_string();
+        let reason = reason.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = cs.publish_ban(&ip, &reason, ban_time_seconds).await {
+                log::error!("❌ Failed to publish ban for {} to Consul: {}", ip, e);
+            }
+        });
+    }
+}
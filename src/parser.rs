@@ -21,6 +21,20 @@ pub struct SecurityConfig {
     pub window_seconds: u64,
     #[serde(default = "default_ban_time_seconds")]
     pub ban_time_seconds: usize,
+    #[serde(default = "default_firewall_backend")]
+    pub firewall_backend: String,
+    #[serde(default)]
+    pub cluster_sync_enabled: bool,
+    #[serde(default = "default_consul_url")]
+    pub consul_url: String,
+    #[serde(default = "default_node_id")]
+    pub node_id: String,
+    #[serde(default)]
+    pub kill_existing_connections: bool,
+    #[serde(default)]
+    pub stun_discovery_enabled: bool,
+    #[serde(default = "default_stun_server")]
+    pub stun_server: String,
     #[serde(default)]
     pub whitelist: Vec<String>,
     #[serde(default)]
@@ -41,6 +55,18 @@ fn default_window_seconds() -> u64 {
 fn default_ban_time_seconds() -> usize {
     86400
 }
+fn default_firewall_backend() -> String {
+    "ipset".to_string()
+}
+fn default_consul_url() -> String {
+    "http://127.0.0.1:8500".to_string()
+}
+fn default_node_id() -> String {
+    std::env::var("HOSTNAME").unwrap_or_else(|_| format!("node-{}", std::process::id()))
+}
+fn default_stun_server() -> String {
+    "stun.l.google.com:19302".to_string()
+}
 
 impl Default for SecurityConfig {
     fn default() -> Self {
@@ -51,6 +77,13 @@ impl Default for SecurityConfig {
             max_retries: default_max_retries(),
             window_seconds: default_window_seconds(),
             ban_time_seconds: default_ban_time_seconds(),
+            firewall_backend: default_firewall_backend(),
+            cluster_sync_enabled: false,
+            consul_url: default_consul_url(),
+            node_id: default_node_id(),
+            kill_existing_connections: false,
+            stun_discovery_enabled: false,
+            stun_server: default_stun_server(),
             whitelist: vec![],
             bad_user_agents: vec![],
             instant_ban: vec![],
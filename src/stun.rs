@@ -0,0 +1,104 @@
+use anyhow::{Result, anyhow};
+use std::net::{Ipv4Addr, UdpSocket};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+const MAGIC_COOKIE: u32 = 0x2112A442;
+const BINDING_REQUEST: u16 = 0x0001;
+const XOR_MAPPED_ADDRESS: u16 = 0x0020;
+
+/// How often to re-run STUN discovery, since dynamic/NAT-assigned public IPs
+/// can change while the daemon is running.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Send a single STUN binding request to `stun_server` and return the public
+/// IPv4 address reported back in the `XOR-MAPPED-ADDRESS` attribute.
+fn discover_public_ip(stun_server: &str) -> Result<Ipv4Addr> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_read_timeout(Some(Duration::from_secs(5)))?;
+    socket.connect(stun_server)?;
+
+    let transaction_id: [u8; 12] = rand::random();
+    let mut request = Vec::with_capacity(20);
+    request.extend_from_slice(&BINDING_REQUEST.to_be_bytes());
+    request.extend_from_slice(&0u16.to_be_bytes()); // message length, no attributes
+    request.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    request.extend_from_slice(&transaction_id);
+
+    socket.send(&request)?;
+
+    let mut buf = [0u8; 512];
+    let len = socket.recv(&mut buf)?;
+    parse_binding_response(&buf[..len], &transaction_id)
+}
+
+fn parse_binding_response(resp: &[u8], transaction_id: &[u8; 12]) -> Result<Ipv4Addr> {
+    if resp.len() < 20 {
+        return Err(anyhow!("STUN response too short"));
+    }
+    if &resp[8..20] != transaction_id {
+        return Err(anyhow!("STUN transaction ID mismatch"));
+    }
+
+    let mut offset = 20;
+    while offset + 4 <= resp.len() {
+        let attr_type = u16::from_be_bytes([resp[offset], resp[offset + 1]]);
+        let attr_len = u16::from_be_bytes([resp[offset + 2], resp[offset + 3]]) as usize;
+        let value_start = offset + 4;
+        let value_end = value_start + attr_len;
+        if value_end > resp.len() {
+            break;
+        }
+
+        if attr_type == XOR_MAPPED_ADDRESS {
+            let value = &resp[value_start..value_end];
+            // Byte 0 reserved, byte 1 address family (0x01 = IPv4), then
+            // 2 bytes X-Port, 4 bytes X-Address, both XOR'd with the cookie.
+            if value.len() >= 8 && value[1] == 0x01 {
+                let xaddr = u32::from_be_bytes([value[4], value[5], value[6], value[7]]);
+                return Ok(Ipv4Addr::from(xaddr ^ MAGIC_COOKIE));
+            }
+        }
+
+        // Attributes are padded out to a 4-byte boundary.
+        offset = value_end + ((4 - (attr_len % 4)) % 4);
+    }
+
+    Err(anyhow!("No XOR-MAPPED-ADDRESS attribute in STUN response"))
+}
+
+/// Spawn a background task that periodically re-discovers the node's public
+/// IP and stores it in `discovered_ip`. Deliberately kept separate from the
+/// hot-reloadable `SecurityConfig` (whose `whitelist` field gets wholesale
+/// replaced by `Parser::load_config` on every config file change) so a
+/// reload can never silently drop the discovered address. The whitelist
+/// checks in `main`'s processing loop consult both `config.whitelist` and
+/// this value before banning an IP.
+pub fn spawn_refresh(discovered_ip: Arc<RwLock<Option<String>>>, stun_server: String) {
+    tokio::spawn(async move {
+        loop {
+            let server = stun_server.clone();
+            let result = tokio::task::spawn_blocking(move || discover_public_ip(&server)).await;
+
+            match result {
+                Ok(Ok(ip)) => {
+                    let ip_str = ip.to_string();
+                    let mut current = discovered_ip.write().unwrap();
+                    if current.as_deref() != Some(ip_str.as_str()) {
+                        log::info!(
+                            "🧭 STUN discovered public IP {} via {} — whitelisted",
+                            ip_str,
+                            stun_server
+                        );
+                        *current = Some(ip_str);
+                    } else {
+                        log::debug!("🧭 STUN confirmed public IP: {}", ip_str);
+                    }
+                }
+                Ok(Err(e)) => log::warn!("STUN public IP discovery failed: {}", e),
+                Err(e) => log::error!("STUN discovery task panicked: {}", e),
+            }
+            tokio::time::sleep(REFRESH_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,98 @@
+use dashmap::DashMap;
+use sd_notify::NotifyState;
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Counters surfaced to systemd via `STATUS=` lines. Cheap to update from the
+/// hot path since every field is a plain atomic.
+#[derive(Default)]
+pub struct ServiceStats {
+    pub lines_processed: AtomicU64,
+    pub bans_issued: AtomicU64,
+}
+
+impl ServiceStats {
+    pub fn record_line(&self) {
+        self.lines_processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_ban(&self) {
+        self.bans_issued.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Tell systemd the service finished starting up. A no-op (`sd_notify` reports
+/// success without talking to anything) when `NOTIFY_SOCKET` isn't set, e.g.
+/// when running outside of systemd.
+pub fn notify_ready() {
+    if let Err(e) = sd_notify::notify(false, &[NotifyState::Ready]) {
+        log::debug!("sd_notify READY failed (not running under systemd?): {}", e);
+    }
+}
+
+pub fn notify_stopping() {
+    if let Err(e) = sd_notify::notify(false, &[NotifyState::Stopping]) {
+        log::debug!("sd_notify STOPPING failed: {}", e);
+    }
+}
+
+fn notify_status(stats: &ServiceStats, tracked_ips: usize) {
+    let status = format!(
+        "Processed {} lines | Tracking {} IPs | {} bans issued",
+        stats.lines_processed.load(Ordering::Relaxed),
+        tracked_ips,
+        stats.bans_issued.load(Ordering::Relaxed)
+    );
+    if let Err(e) = sd_notify::notify(false, &[NotifyState::Status(&status)]) {
+        log::debug!("sd_notify STATUS failed: {}", e);
+    }
+}
+
+/// Push a `STATUS=` line reporting current throughput. Called after every ban
+/// so operators can watch the counters move in `systemctl status`.
+pub fn report_status(stats: &Arc<ServiceStats>, detector_state: &DashMap<String, crate::IpState>) {
+    notify_status(stats, detector_state.len());
+}
+
+/// If `WATCHDOG_USEC` is present in the environment, spawn a task that pings
+/// systemd at half the watchdog interval so a hung main loop gets restarted
+/// by the service manager instead of silently wedging.
+pub fn spawn_watchdog(stats: Arc<ServiceStats>, detector_state: Arc<DashMap<String, crate::IpState>>) {
+    let watchdog_usec: u64 = match env::var("WATCHDOG_USEC") {
+        Ok(v) => match v.parse() {
+            Ok(usec) => usec,
+            Err(_) => {
+                log::warn!("WATCHDOG_USEC is set but not a valid integer: {}", v);
+                return;
+            }
+        },
+        Err(_) => return,
+    };
+
+    let interval = Duration::from_micros(watchdog_usec / 2);
+    if interval.is_zero() {
+        log::warn!(
+            "WATCHDOG_USEC={} is too small to produce a usable ping interval, skipping watchdog ping task",
+            watchdog_usec
+        );
+        return;
+    }
+
+    log::info!(
+        "🐕 systemd watchdog enabled, pinging every {:?}",
+        interval
+    );
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            notify_status(&stats, detector_state.len());
+            if let Err(e) = sd_notify::notify(false, &[NotifyState::Watchdog]) {
+                log::error!("sd_notify WATCHDOG failed: {}", e);
+            }
+        }
+    });
+}